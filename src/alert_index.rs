@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use mysql_async::{params, prelude::*, Pool};
+use reqwest::Client;
+use tokio::sync::RwLock;
+use tokio::time::{interval, Duration};
+
+use crate::errors::*;
+use crate::notify::{Notifier, NotifierConfig};
+use crate::trigger::AlertTrigger;
+
+const MIN_TRIGGER_VERSION: i32 = 0;
+const MAX_TRIGGER_VERSION: i32 = 0;
+const WILDCARD_ITEM_ID: i32 = -1;
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+pub struct UserAlert {
+    pub name: String,
+    pub notifiers: Vec<Arc<dyn Notifier>>,
+}
+
+type AlertBucket = Vec<(UserAlert, AlertTrigger)>;
+
+async fn load_alerts(pool: &Pool, client: &Client) -> Result<HashMap<(i32, i32), AlertBucket>> {
+    let mut conn = pool.get_conn().await?;
+    let rows: Vec<(i32, i32, String, String, String)> = r"SELECT `world_id`, `item_id`, `name`, `trigger`, `notifiers` FROM `users_alerts_next` WHERE `trigger_version` >= :min_trigger_version AND `trigger_version` <= :max_trigger_version".with(params! {
+        "min_trigger_version" => MIN_TRIGGER_VERSION,
+        "max_trigger_version" => MAX_TRIGGER_VERSION,
+    })
+        .map(&mut conn, |(world_id, item_id, name, trigger, notifiers)| {
+            (world_id, item_id, name, trigger, notifiers)
+        })
+        .await?;
+
+    let mut index: HashMap<(i32, i32), AlertBucket> = HashMap::new();
+    for (world_id, item_id, name, trigger, notifiers) in rows {
+        let alert_trigger: AlertTrigger = match serde_json::from_str(&trigger) {
+            Ok(alert_trigger) => alert_trigger,
+            Err(err) => {
+                println!("Skipping malformed trigger for alert '{}': {:?}", name, err);
+                continue;
+            }
+        };
+        let notifier_configs: Vec<NotifierConfig> = match serde_json::from_str(&notifiers) {
+            Ok(notifier_configs) => notifier_configs,
+            Err(err) => {
+                println!("Skipping malformed notifiers for alert '{}': {:?}", name, err);
+                continue;
+            }
+        };
+        let alert = UserAlert {
+            name,
+            notifiers: notifier_configs
+                .iter()
+                .map(|config| config.build(client.clone()))
+                .collect(),
+        };
+        index
+            .entry((world_id, item_id))
+            .or_insert_with(Vec::new)
+            .push((alert, alert_trigger));
+    }
+
+    Ok(index)
+}
+
+// Shared, refreshable view of `users_alerts_next`, keyed by `(world_id, item_id)` so the
+// hot path can look up matching alerts in memory instead of hitting the database per event.
+// Alerts with `item_id = -1` match every item in that world and are stored under the
+// `(world_id, -1)` key, so a lookup checks that key in addition to the exact item.
+#[derive(Clone)]
+pub struct AlertIndex {
+    inner: Arc<RwLock<HashMap<(i32, i32), AlertBucket>>>,
+}
+
+impl AlertIndex {
+    pub async fn load(pool: &Pool, client: &Client) -> Result<Self> {
+        let index = load_alerts(pool, client).await?;
+        Ok(Self {
+            inner: Arc::new(RwLock::new(index)),
+        })
+    }
+
+    pub async fn refresh(&self, pool: &Pool, client: &Client) -> Result<()> {
+        let index = load_alerts(pool, client).await?;
+        *self.inner.write().await = index;
+        Ok(())
+    }
+
+    // Spawns a background task that re-queries the table and atomically swaps the map
+    // in on an interval, so the index stays eventually-consistent without blocking events.
+    pub fn spawn_refresh(&self, pool: Pool, client: Client) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            let mut refresh_interval = interval(REFRESH_INTERVAL);
+            loop {
+                refresh_interval.tick().await;
+                if let Err(err) = this.refresh(&pool, &client).await {
+                    println!("Failed to refresh alert index: {:?}", err);
+                }
+            }
+        });
+    }
+
+    pub async fn alerts_for(&self, world_id: i32, item_id: i32) -> AlertBucket {
+        let index = self.inner.read().await;
+        let mut alerts = index.get(&(world_id, item_id)).cloned().unwrap_or_default();
+        if item_id != WILDCARD_ITEM_ID {
+            if let Some(wildcard) = index.get(&(world_id, WILDCARD_ITEM_ID)) {
+                alerts.extend(wildcard.iter().cloned());
+            }
+        }
+        alerts
+    }
+}
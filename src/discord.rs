@@ -0,0 +1,28 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct DiscordWebhookPayload<'a> {
+    pub embeds: Vec<DiscordEmbed<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiscordEmbed<'a> {
+    pub url: &'a str,
+    pub title: &'a str,
+    pub description: &'a str,
+    pub color: u32,
+    pub footer: DiscordEmbedFooter<'a>,
+    pub author: DiscordEmbedAuthor<'a>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiscordEmbedFooter<'a> {
+    pub text: &'a str,
+    pub icon_url: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiscordEmbedAuthor<'a> {
+    pub name: &'a str,
+    pub icon_url: &'a str,
+}
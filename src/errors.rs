@@ -0,0 +1,13 @@
+error_chain::error_chain! {
+    foreign_links {
+        Io(std::io::Error);
+        Var(std::env::VarError);
+        UrlParse(url::ParseError);
+        Tungstenite(tokio_tungstenite::tungstenite::Error);
+        BsonDecode(bson::de::Error);
+        BsonEncode(bson::ser::Error);
+        Json(serde_json::Error);
+        MySql(mysql_async::Error);
+        Reqwest(reqwest::Error);
+    }
+}
@@ -1,60 +1,35 @@
 use std::env;
 use std::io::Cursor;
+use std::time::Instant;
 
-use crate::discord::*;
+use crate::alert_index::*;
 use crate::errors::*;
+use crate::notify::*;
 use crate::trigger::*;
 use crate::universalis::*;
 use crate::xivapi::*;
 use bson::Document;
 use dotenv::dotenv;
-use futures_util::{pin_mut, SinkExt, StreamExt};
-use mysql_async::{params, prelude::*, Pool};
+use futures_util::{SinkExt, StreamExt};
+use mysql_async::Pool;
 use reqwest::Client;
+use tokio::time::{interval, sleep, Duration, Instant as TokioInstant};
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use url::Url;
 
+mod alert_index;
 mod discord;
 mod errors;
+mod notify;
 mod trigger;
 mod universalis;
 mod xivapi;
 
-const MIN_TRIGGER_VERSION: i32 = 0;
-const MAX_TRIGGER_VERSION: i32 = 0;
-
-#[derive(Debug)]
-struct UserAlert {
-    name: String,
-    discord_webhook: Option<String>,
-    trigger: String,
-}
-
-async fn get_alerts_for_world_item(
-    world_id: i32,
-    item_id: i32,
-    pool: &Pool,
-) -> Result<Vec<(UserAlert, AlertTrigger)>> {
-    // TODO: Add caching for this?
-    let mut conn = pool.get_conn().await?;
-    let alerts = r"SELECT `name`, `discord_webhook`, `trigger` FROM `users_alerts_next` WHERE `world_id` = :world_id AND (`item_id` = :item_id OR `item_id` = -1) AND `trigger_version` >= :min_trigger_version AND `trigger_version` <= :max_trigger_version".with(params! {
-        "world_id" => world_id,
-        "item_id" => item_id,
-        "min_trigger_version" => MIN_TRIGGER_VERSION,
-        "max_trigger_version" => MAX_TRIGGER_VERSION,
-    })
-        .map(&mut conn, |(name, discord_webhook, trigger)| {
-            let alert = UserAlert {
-                name,
-                discord_webhook,
-                trigger,
-            };
-            // TODO: Don't unwrap this
-            let alert_trigger: AlertTrigger = serde_json::from_str(&alert.trigger).unwrap();
-            (alert, alert_trigger)
-        })
-        .await?;
-    Ok(alerts)
-}
+const INITIAL_RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(60);
+const HEALTHY_CONNECTION_THRESHOLD: Duration = Duration::from_secs(60);
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+const PONG_TIMEOUT: Duration = Duration::from_secs(10);
 
 fn get_universalis_url(item_id: i32, world_name: &str) -> String {
     format!(
@@ -63,7 +38,7 @@ fn get_universalis_url(item_id: i32, world_name: &str) -> String {
     )
 }
 
-async fn send_discord_message(
+async fn dispatch_alert(
     item_id: i32,
     world_id: i32,
     alert: &UserAlert,
@@ -71,120 +46,193 @@ async fn send_discord_message(
     trigger_result: f32,
     client: &Client,
 ) -> Result<()> {
-    let discord_webhook = alert.discord_webhook.as_ref();
-    if discord_webhook.is_none() {
-        return Ok(());
-    }
-    let discord_webhook = discord_webhook.unwrap();
-
-    let item = get_item(item_id, &client).await?;
-    let world = get_world(world_id, &client).await?;
+    let item = get_item(item_id, client).await?;
+    let world = get_world(world_id, client).await?;
     let market_url = get_universalis_url(item_id, &world.name);
-    let embed_title = format!("Alert triggered for {} on {}", item.name, world.name);
-    let embed_footer_text = format!("universalis.app | {} | All prices include GST", alert.name);
-    let embed_description = format!("One of your alerts has been triggered for the following reason(s):\n```c\n{}\n\nValue: {}```\nYou can view the item page on Universalis by clicking [this link]({}).", trigger, trigger_result, market_url);
-    let payload = DiscordWebhookPayload {
-        embeds: [DiscordEmbed {
-            url: &market_url,
-            title: &embed_title,
-            description: &embed_description,
-            color: 0xBD983A,
-            footer: DiscordEmbedFooter {
-                text: &embed_footer_text,
-                icon_url: "https://universalis.app/favicon.png",
-            },
-            author: DiscordEmbedAuthor {
-                name: "Universalis Alert!",
-                icon_url: "https://cdn.discordapp.com/emojis/474543539771015168.png",
-            },
-        }]
-        .to_vec(),
+    let ctx = AlertContext {
+        alert_name: &alert.name,
+        item_name: &item.name,
+        world_name: &world.name,
+        trigger,
+        trigger_result,
+        market_url,
     };
-    let serialized = serde_json::to_string(&payload)?;
 
-    client
-        .post(discord_webhook)
-        .header("Content-Type", "application/json")
-        .body(serialized)
-        .send()
-        .await?;
+    for notifier in &alert.notifiers {
+        if let Err(err) = notifier.notify(&ctx).await {
+            println!("Notifier failed for alert '{}': {:?}", alert.name, err);
+        }
+    }
 
     Ok(())
 }
 
-fn parse_event_from_message(data: &[u8]) -> Result<ListingsAddEvent> {
+fn parse_event_from_message(data: &[u8]) -> Result<MarketEvent> {
     let mut reader = Cursor::new(data.clone());
     let document = Document::from_reader(&mut reader)?;
-    let ev: ListingsAddEvent = bson::from_bson(document.into())?;
+    let ev: MarketEvent = bson::from_bson(document.into())?;
     Ok(ev)
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    dotenv().ok();
-
-    // TODO: Enable tokio tracing
-    // TODO: Add metrics
-    // TODO: Add logging
-    // TODO: Log failures instead of just yeeting errors
-
-    let database_url = env::var("UNIVERSALIS_ALERTS_DB")?;
-    let pool = Pool::new(database_url.as_str());
-
-    let connect_addr = env::var("UNIVERSALIS_ALERTS_WS")?;
-    let url = url::Url::parse(&connect_addr)?;
+async fn handle_event(data: Vec<u8>, alert_index: AlertIndex, client: Client) {
+    let ev = parse_event_from_message(&data);
+    if let Err(err) = ev {
+        println!("{:?}", err);
+        return;
+    }
+    let ev = ev.unwrap();
+
+    let alerts = alert_index.alerts_for(ev.world_id(), ev.item_id()).await;
+    for (alert, trigger) in alerts {
+        // Only evaluate triggers that target the event class we just received
+        if trigger.event_class() != ev.class() {
+            continue;
+        }
+
+        let trigger_result = match &ev {
+            MarketEvent::ListingsAdd(ev) => trigger.evaluate_listings(&ev.listings),
+            MarketEvent::SalesAdd(ev) => trigger.evaluate_sales(&ev.sales),
+            MarketEvent::ListingsRemove(ev) => trigger.evaluate_removals(&ev.listings),
+        };
+
+        // Dispatch to every configured backend if the trigger condition is met
+        if let Some(trigger_result) = trigger_result {
+            let dispatched = dispatch_alert(
+                ev.item_id(),
+                ev.world_id(),
+                &alert,
+                &trigger,
+                trigger_result,
+                &client,
+            )
+            .await;
+            if let Err(err) = dispatched {
+                println!("{:?}", err);
+            }
+        }
+    }
+}
 
-    // TODO: Attempt to reconnect when the connection drops?
-    let (ws_stream, _) = connect_async(url).await?;
+// Runs a single connect + subscribe + read cycle, returning when the stream ends or a
+// keepalive pong is missed so the caller can reconnect.
+async fn run_connection(
+    url: &Url,
+    channel: &str,
+    alert_index: &AlertIndex,
+    client: &Client,
+) -> Result<()> {
+    let (ws_stream, _) = connect_async(url.clone()).await?;
     println!("WebSocket handshake has been successfully completed");
 
-    let (mut write, read) = ws_stream.split();
+    let (mut write, mut read) = ws_stream.split();
 
+    // One subscription is enough to see listings-add, sales-add and listings-remove:
+    // `UNIVERSALIS_ALERTS_CHANNEL` multiplexes all three onto this channel, and each
+    // message's `event` field is what `MarketEvent` uses to tell them apart.
     let event = SubscribeEvent {
         event: "subscribe",
-        channel: &env::var("UNIVERSALIS_ALERTS_CHANNEL")?,
+        channel,
     };
     let serialized = bson::to_bson(&event)?;
     let mut v: Vec<u8> = Vec::new();
     // TODO: Don't unwrap this
     serialized.as_document().unwrap().to_writer(&mut v)?;
-
-    // TODO: Ping the connection so it doesn't die
     write.send(Message::Binary(v)).await?;
 
-    let client = reqwest::Client::new();
-    let on_message = {
-        read.for_each_concurrent(None, |message| async {
-            // TODO: Don't unwrap these
-            let ev = message
-                .chain_err(|| "failed to receive websocket message")
-                .map(|m| m.into_data())
-                .and_then(|data| parse_event_from_message(&data));
-            if let Err(err) = ev {
-                println!("{:?}", err);
-                return;
+    let mut ping_interval = interval(PING_INTERVAL);
+    let mut awaiting_pong = false;
+    let pong_deadline = sleep(PONG_TIMEOUT);
+    tokio::pin!(pong_deadline);
+
+    loop {
+        tokio::select! {
+            _ = ping_interval.tick() => {
+                write.send(Message::Ping(Vec::new())).await?;
+                pong_deadline.as_mut().reset(TokioInstant::now() + PONG_TIMEOUT);
+                awaiting_pong = true;
             }
-            let ev = ev.unwrap();
-
-            let alerts = get_alerts_for_world_item(ev.world_id, ev.item_id, &pool)
-                .await
-                .unwrap();
-            for (alert, trigger) in alerts {
-                // Send webhook message if all trigger conditions are met
-                trigger
-                    .evaluate(&ev.listings)
-                    .map(|tr| {
-                        send_discord_message(ev.item_id, ev.world_id, &alert, &trigger, tr, &client)
-                    })
-                    .unwrap()
-                    .await
-                    .unwrap();
+            _ = &mut pong_deadline, if awaiting_pong => {
+                return Err("keepalive pong timed out".into());
             }
-        })
-    };
+            message = read.next() => {
+                let message = match message {
+                    Some(message) => message,
+                    None => return Ok(()),
+                };
+
+                let message = message?;
+                if message.is_pong() {
+                    awaiting_pong = false;
+                    continue;
+                }
+                if !message.is_binary() {
+                    continue;
+                }
+
+                tokio::spawn(handle_event(
+                    message.into_data(),
+                    alert_index.clone(),
+                    client.clone(),
+                ));
+            }
+        }
+    }
+}
 
-    pin_mut!(on_message);
-    on_message.await;
+// Wraps `run_connection` in a reconnect loop with exponential backoff, so a dropped
+// connection or a missed keepalive pong doesn't take the daemon down with it.
+async fn run_with_reconnect(
+    url: &Url,
+    channel: &str,
+    alert_index: AlertIndex,
+    client: Client,
+) -> Result<()> {
+    let mut backoff = INITIAL_RECONNECT_BACKOFF;
+
+    loop {
+        let connected_at = Instant::now();
+        match run_connection(url, channel, &alert_index, &client).await {
+            Ok(()) => println!("WebSocket connection closed, reconnecting"),
+            Err(err) => println!("WebSocket connection dropped: {:?}", err),
+        }
+
+        if connected_at.elapsed() >= HEALTHY_CONNECTION_THRESHOLD {
+            backoff = INITIAL_RECONNECT_BACKOFF;
+        }
+
+        let jitter = Duration::from_millis(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| u64::from(d.subsec_millis()) % 250)
+                .unwrap_or(0),
+        );
+        let delay = backoff + jitter;
+        println!("Reconnecting in {:?}", delay);
+        sleep(delay).await;
+
+        backoff = std::cmp::min(backoff * 2, MAX_RECONNECT_BACKOFF);
+    }
+}
 
-    Ok(())
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv().ok();
+
+    // TODO: Enable tokio tracing
+    // TODO: Add metrics
+    // TODO: Add logging
+    // TODO: Log failures instead of just yeeting errors
+
+    let client = reqwest::Client::new();
+
+    let database_url = env::var("UNIVERSALIS_ALERTS_DB")?;
+    let pool = Pool::new(database_url.as_str());
+    let alert_index = AlertIndex::load(&pool, &client).await?;
+    alert_index.spawn_refresh(pool, client.clone());
+
+    let connect_addr = env::var("UNIVERSALIS_ALERTS_WS")?;
+    let url = Url::parse(&connect_addr)?;
+    let channel = env::var("UNIVERSALIS_ALERTS_CHANNEL")?;
+
+    run_with_reconnect(&url, &channel, alert_index, client).await
 }
@@ -0,0 +1,237 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::discord::*;
+use crate::errors::*;
+use crate::trigger::AlertTrigger;
+
+// Everything a `Notifier` needs to describe an alert firing, independent of which
+// backend(s) end up delivering it.
+pub struct AlertContext<'a> {
+    pub alert_name: &'a str,
+    pub item_name: &'a str,
+    pub world_name: &'a str,
+    pub trigger: &'a AlertTrigger,
+    pub trigger_result: f32,
+    pub market_url: String,
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, ctx: &AlertContext) -> Result<()>;
+}
+
+// Row-level description of which backend(s) an alert should fire, as stored in the
+// `notifiers` column. Parsed once at load time, same as `AlertTrigger`; see
+// `NotifierConfig::build`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NotifierConfig {
+    Discord { webhook_url: String },
+    Webhook { url: String, body_template: String },
+    ChatService { token: String, room_id: String },
+}
+
+impl NotifierConfig {
+    // Builds the concrete notifier for this config, reusing the caller's `Client` rather
+    // than opening a fresh connection pool per notifier.
+    pub fn build(&self, client: Client) -> Arc<dyn Notifier> {
+        match self {
+            NotifierConfig::Discord { webhook_url } => Arc::new(DiscordNotifier {
+                client,
+                webhook_url: webhook_url.clone(),
+            }),
+            NotifierConfig::Webhook { url, body_template } => Arc::new(WebhookNotifier {
+                client,
+                url: url.clone(),
+                body_template: body_template.clone(),
+            }),
+            NotifierConfig::ChatService { token, room_id } => Arc::new(ChatServiceNotifier {
+                client,
+                token: token.clone(),
+                room_id: room_id.clone(),
+            }),
+        }
+    }
+}
+
+pub struct DiscordNotifier {
+    client: Client,
+    webhook_url: String,
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    async fn notify(&self, ctx: &AlertContext) -> Result<()> {
+        let embed_title = format!("Alert triggered for {} on {}", ctx.item_name, ctx.world_name);
+        let embed_footer_text = format!(
+            "universalis.app | {} | All prices include GST",
+            ctx.alert_name
+        );
+        let embed_description = format!("One of your alerts has been triggered for the following reason(s):\n```c\n{}\n\nValue: {}```\nYou can view the item page on Universalis by clicking [this link]({}).", ctx.trigger, ctx.trigger_result, ctx.market_url);
+        let payload = DiscordWebhookPayload {
+            embeds: [DiscordEmbed {
+                url: &ctx.market_url,
+                title: &embed_title,
+                description: &embed_description,
+                color: 0xBD983A,
+                footer: DiscordEmbedFooter {
+                    text: &embed_footer_text,
+                    icon_url: "https://universalis.app/favicon.png",
+                },
+                author: DiscordEmbedAuthor {
+                    name: "Universalis Alert!",
+                    icon_url: "https://cdn.discordapp.com/emojis/474543539771015168.png",
+                },
+            }]
+            .to_vec(),
+        };
+        let serialized = serde_json::to_string(&payload)?;
+
+        self.client
+            .post(&self.webhook_url)
+            .header("Content-Type", "application/json")
+            .body(serialized)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+// Fires a templated JSON body at an arbitrary URL, for self-hosted integrations that
+// don't speak Discord's embed format. `{{item}}`, `{{world}}`, `{{trigger}}`, `{{value}}`
+// and `{{url}}` in the template are substituted before the body is sent.
+pub struct WebhookNotifier {
+    client: Client,
+    url: String,
+    body_template: String,
+}
+
+impl WebhookNotifier {
+    fn render_body(&self, ctx: &AlertContext) -> String {
+        self.body_template
+            .replace("{{item}}", ctx.item_name)
+            .replace("{{world}}", ctx.world_name)
+            .replace("{{trigger}}", &ctx.trigger.to_string())
+            .replace("{{value}}", &ctx.trigger_result.to_string())
+            .replace("{{url}}", &ctx.market_url)
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, ctx: &AlertContext) -> Result<()> {
+        let body = self.render_body(ctx);
+
+        self.client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trigger::AlertTrigger;
+
+    fn ctx(trigger: &AlertTrigger) -> AlertContext {
+        AlertContext {
+            alert_name: "My Alert",
+            item_name: "Item",
+            world_name: "Gilgamesh",
+            trigger,
+            trigger_result: 87.0,
+            market_url: "https://universalis.app/market/5057".to_string(),
+        }
+    }
+
+    fn webhook_notifier(body_template: &str) -> WebhookNotifier {
+        WebhookNotifier {
+            client: Client::new(),
+            url: "https://example.com/hook".to_string(),
+            body_template: body_template.to_string(),
+        }
+    }
+
+    #[test]
+    fn render_body_substitutes_repeated_placeholders() {
+        let trigger = AlertTrigger::PriceBelow { price: 100 };
+        let notifier = webhook_notifier(r#"{"item": "{{item}}", "again": "{{item}}"}"#);
+
+        let body = notifier.render_body(&ctx(&trigger));
+
+        assert_eq!(body, r#"{"item": "Item", "again": "Item"}"#);
+    }
+
+    #[test]
+    fn render_body_substitutes_only_placeholders_present_in_template() {
+        let trigger = AlertTrigger::PriceBelow { price: 100 };
+        let notifier = webhook_notifier(r#"{"item": "{{item}}", "world": "{{world}}"}"#);
+
+        let body = notifier.render_body(&ctx(&trigger));
+
+        assert_eq!(body, r#"{"item": "Item", "world": "Gilgamesh"}"#);
+    }
+
+    #[test]
+    fn render_body_substitutes_all_known_placeholders() {
+        let trigger = AlertTrigger::PriceBelow { price: 100 };
+        let notifier = webhook_notifier(
+            "{{item}}/{{world}}/{{trigger}}/{{value}}/{{url}}",
+        );
+
+        let body = notifier.render_body(&ctx(&trigger));
+
+        assert_eq!(
+            body,
+            "Item/Gilgamesh/Price below 100/87/https://universalis.app/market/5057"
+        );
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ChatServiceMessage<'a> {
+    #[serde(rename = "roomId")]
+    room_id: &'a str,
+    markdown: String,
+}
+
+// Posts a markdown message to a chat-service room, modeled on Webex's room-message API:
+// bearer-token auth, a target room ID, and a markdown body.
+pub struct ChatServiceNotifier {
+    client: Client,
+    token: String,
+    room_id: String,
+}
+
+#[async_trait]
+impl Notifier for ChatServiceNotifier {
+    async fn notify(&self, ctx: &AlertContext) -> Result<()> {
+        let markdown = format!(
+            "**Alert triggered for {} on {}**\n\n{}\n\nValue: {}\n\n[View on Universalis]({})",
+            ctx.item_name, ctx.world_name, ctx.trigger, ctx.trigger_result, ctx.market_url
+        );
+        let payload = ChatServiceMessage {
+            room_id: &self.room_id,
+            markdown,
+        };
+
+        self.client
+            .post("https://webexapis.com/v1/messages")
+            .bearer_auth(&self.token)
+            .json(&payload)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::universalis::{EventClass, Listing, Sale};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AlertTrigger {
+    PriceBelow { price: i32 },
+    PriceAbove { price: i32 },
+    QuantityAvailableAbove { quantity: i32 },
+    SalePriceBelow { price: i32 },
+    SaleQuantityAbove { quantity: i32 },
+    QuantityRemovedAbove { quantity: i32 },
+}
+
+impl AlertTrigger {
+    // The event class this trigger should be evaluated against. The dispatcher in
+    // `main` uses this to skip triggers that don't apply to an incoming event.
+    pub fn event_class(&self) -> EventClass {
+        match self {
+            AlertTrigger::PriceBelow { .. }
+            | AlertTrigger::PriceAbove { .. }
+            | AlertTrigger::QuantityAvailableAbove { .. } => EventClass::ListingsAdd,
+            AlertTrigger::SalePriceBelow { .. } | AlertTrigger::SaleQuantityAbove { .. } => {
+                EventClass::SalesAdd
+            }
+            AlertTrigger::QuantityRemovedAbove { .. } => EventClass::ListingsRemove,
+        }
+    }
+
+    // Evaluates the trigger against a set of listings, returning the value that
+    // satisfied it if the trigger fired.
+    pub fn evaluate_listings(&self, listings: &[Listing]) -> Option<f32> {
+        match self {
+            AlertTrigger::PriceBelow { price } => listings
+                .iter()
+                .map(|listing| listing.price_per_unit)
+                .min()
+                .filter(|lowest| lowest < price)
+                .map(|lowest| lowest as f32),
+            AlertTrigger::PriceAbove { price } => listings
+                .iter()
+                .map(|listing| listing.price_per_unit)
+                .max()
+                .filter(|highest| highest > price)
+                .map(|highest| highest as f32),
+            AlertTrigger::QuantityAvailableAbove { quantity } => {
+                let available: i32 = listings.iter().map(|listing| listing.quantity).sum();
+                if available > *quantity {
+                    Some(available as f32)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // Evaluates the trigger against a set of sales, returning the value that satisfied
+    // it if the trigger fired.
+    pub fn evaluate_sales(&self, sales: &[Sale]) -> Option<f32> {
+        match self {
+            AlertTrigger::SalePriceBelow { price } => sales
+                .iter()
+                .map(|sale| sale.price_per_unit)
+                .min()
+                .filter(|lowest| lowest < price)
+                .map(|lowest| lowest as f32),
+            AlertTrigger::SaleQuantityAbove { quantity } => {
+                let sold: i32 = sales.iter().map(|sale| sale.quantity).sum();
+                if sold > *quantity {
+                    Some(sold as f32)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // Evaluates the trigger against a set of removed listings, returning the value that
+    // satisfied it if the trigger fired.
+    pub fn evaluate_removals(&self, removed: &[Listing]) -> Option<f32> {
+        match self {
+            AlertTrigger::QuantityRemovedAbove { quantity } => {
+                let removed_quantity: i32 = removed.iter().map(|listing| listing.quantity).sum();
+                if removed_quantity > *quantity {
+                    Some(removed_quantity as f32)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AlertTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AlertTrigger::PriceBelow { price } => write!(f, "Price below {}", price),
+            AlertTrigger::PriceAbove { price } => write!(f, "Price above {}", price),
+            AlertTrigger::QuantityAvailableAbove { quantity } => {
+                write!(f, "Quantity available above {}", quantity)
+            }
+            AlertTrigger::SalePriceBelow { price } => write!(f, "Sale price below {}", price),
+            AlertTrigger::SaleQuantityAbove { quantity } => {
+                write!(f, "Sale quantity above {}", quantity)
+            }
+            AlertTrigger::QuantityRemovedAbove { quantity } => {
+                write!(f, "Quantity removed above {}", quantity)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(quantity: i32) -> Listing {
+        Listing {
+            price_per_unit: quantity, // reused as the price in price-based cases below
+            quantity,
+            hq: false,
+            retainer_name: "Example".to_string(),
+        }
+    }
+
+    fn sale(price_per_unit: i32, quantity: i32) -> Sale {
+        Sale {
+            price_per_unit,
+            quantity,
+            buyer_world_id: 74,
+            timestamp: 1_690_000_000,
+        }
+    }
+
+    #[test]
+    fn sale_price_below_fires_only_strictly_under_threshold() {
+        let trigger = AlertTrigger::SalePriceBelow { price: 100 };
+        assert_eq!(trigger.event_class(), EventClass::SalesAdd);
+
+        let cases = [(101, None), (100, None), (99, Some(99.0))];
+        for (price, expected) in cases {
+            assert_eq!(
+                trigger.evaluate_sales(&[sale(price, 1)]),
+                expected,
+                "price = {}",
+                price
+            );
+        }
+    }
+
+    #[test]
+    fn sale_quantity_above_fires_only_strictly_over_threshold() {
+        let trigger = AlertTrigger::SaleQuantityAbove { quantity: 10 };
+        assert_eq!(trigger.event_class(), EventClass::SalesAdd);
+
+        let cases = [(9, None), (10, None), (11, Some(11.0))];
+        for (quantity, expected) in cases {
+            assert_eq!(
+                trigger.evaluate_sales(&[sale(100, quantity)]),
+                expected,
+                "quantity = {}",
+                quantity
+            );
+        }
+    }
+
+    #[test]
+    fn quantity_removed_above_fires_only_strictly_over_threshold() {
+        let trigger = AlertTrigger::QuantityRemovedAbove { quantity: 5 };
+        assert_eq!(trigger.event_class(), EventClass::ListingsRemove);
+
+        let cases = [(4, None), (5, None), (6, Some(6.0))];
+        for (quantity, expected) in cases {
+            assert_eq!(
+                trigger.evaluate_removals(&[listing(quantity)]),
+                expected,
+                "quantity = {}",
+                quantity
+            );
+        }
+    }
+
+    #[test]
+    fn sales_and_removal_triggers_do_not_evaluate_against_listings() {
+        let sale_trigger = AlertTrigger::SalePriceBelow { price: 100 };
+        let removal_trigger = AlertTrigger::QuantityRemovedAbove { quantity: 0 };
+
+        assert_eq!(sale_trigger.evaluate_listings(&[listing(1)]), None);
+        assert_eq!(removal_trigger.evaluate_listings(&[listing(1)]), None);
+        assert_eq!(removal_trigger.evaluate_sales(&[sale(1, 1)]), None);
+    }
+}
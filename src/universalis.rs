@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+pub struct SubscribeEvent<'a> {
+    pub event: &'a str,
+    pub channel: &'a str,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Listing {
+    #[serde(rename = "pricePerUnit")]
+    pub price_per_unit: i32,
+    pub quantity: i32,
+    pub hq: bool,
+    #[serde(rename = "retainerName")]
+    pub retainer_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Sale {
+    #[serde(rename = "pricePerUnit")]
+    pub price_per_unit: i32,
+    pub quantity: i32,
+    #[serde(rename = "worldID")]
+    pub buyer_world_id: i32,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListingsAddEvent {
+    #[serde(rename = "worldID")]
+    pub world_id: i32,
+    #[serde(rename = "itemID")]
+    pub item_id: i32,
+    pub listings: Vec<Listing>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SalesAddEvent {
+    #[serde(rename = "worldID")]
+    pub world_id: i32,
+    #[serde(rename = "itemID")]
+    pub item_id: i32,
+    pub sales: Vec<Sale>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListingsRemoveEvent {
+    #[serde(rename = "worldID")]
+    pub world_id: i32,
+    #[serde(rename = "itemID")]
+    pub item_id: i32,
+    pub listings: Vec<Listing>,
+}
+
+// Which channel a `MarketEvent` came in on. A trigger declares the one it wants to be
+// evaluated against; the dispatcher in `main` uses this to route decoded events to the
+// alerts that care about them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventClass {
+    ListingsAdd,
+    SalesAdd,
+    ListingsRemove,
+}
+
+// A decoded Universalis market event, discriminated by the `event` field in the BSON
+// message so `listings/add`, `sales/add` and `listings/remove` can carry their own shape
+// instead of being force-decoded as a listings event.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "event")]
+pub enum MarketEvent {
+    #[serde(rename = "listings/add")]
+    ListingsAdd(ListingsAddEvent),
+    #[serde(rename = "sales/add")]
+    SalesAdd(SalesAddEvent),
+    #[serde(rename = "listings/remove")]
+    ListingsRemove(ListingsRemoveEvent),
+}
+
+impl MarketEvent {
+    pub fn world_id(&self) -> i32 {
+        match self {
+            MarketEvent::ListingsAdd(ev) => ev.world_id,
+            MarketEvent::SalesAdd(ev) => ev.world_id,
+            MarketEvent::ListingsRemove(ev) => ev.world_id,
+        }
+    }
+
+    pub fn item_id(&self) -> i32 {
+        match self {
+            MarketEvent::ListingsAdd(ev) => ev.item_id,
+            MarketEvent::SalesAdd(ev) => ev.item_id,
+            MarketEvent::ListingsRemove(ev) => ev.item_id,
+        }
+    }
+
+    pub fn class(&self) -> EventClass {
+        match self {
+            MarketEvent::ListingsAdd(_) => EventClass::ListingsAdd,
+            MarketEvent::SalesAdd(_) => EventClass::SalesAdd,
+            MarketEvent::ListingsRemove(_) => EventClass::ListingsRemove,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::doc;
+
+    fn decode(document: bson::Document) -> MarketEvent {
+        bson::from_bson(document.into()).expect("message should decode")
+    }
+
+    #[test]
+    fn decodes_listings_add() {
+        let ev = decode(doc! {
+            "event": "listings/add",
+            "worldID": 74,
+            "itemID": 5057,
+            "listings": [
+                { "pricePerUnit": 1234, "quantity": 1, "hq": false, "retainerName": "Example" },
+            ],
+        });
+        assert!(matches!(ev, MarketEvent::ListingsAdd(_)));
+        assert_eq!(ev.class(), EventClass::ListingsAdd);
+        assert_eq!(ev.world_id(), 74);
+        assert_eq!(ev.item_id(), 5057);
+    }
+
+    #[test]
+    fn decodes_sales_add() {
+        let ev = decode(doc! {
+            "event": "sales/add",
+            "worldID": 74,
+            "itemID": 5057,
+            "sales": [
+                { "pricePerUnit": 1234, "quantity": 1, "worldID": 74, "timestamp": 1_690_000_000i64 },
+            ],
+        });
+        assert!(matches!(ev, MarketEvent::SalesAdd(_)));
+        assert_eq!(ev.class(), EventClass::SalesAdd);
+    }
+
+    #[test]
+    fn decodes_listings_remove() {
+        let ev = decode(doc! {
+            "event": "listings/remove",
+            "worldID": 74,
+            "itemID": 5057,
+            "listings": [
+                { "pricePerUnit": 1234, "quantity": 1, "hq": false, "retainerName": "Example" },
+            ],
+        });
+        assert!(matches!(ev, MarketEvent::ListingsRemove(_)));
+        assert_eq!(ev.class(), EventClass::ListingsRemove);
+    }
+}
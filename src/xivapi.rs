@@ -0,0 +1,36 @@
+use serde::Deserialize;
+
+use crate::errors::*;
+
+#[derive(Debug, Deserialize)]
+pub struct Item {
+    #[serde(rename = "ID")]
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct World {
+    pub id: i32,
+    pub name: String,
+}
+
+pub async fn get_item(item_id: i32, client: &reqwest::Client) -> Result<Item> {
+    let item = client
+        .get(format!("https://xivapi.com/Item/{}?columns=ID,Name", item_id))
+        .send()
+        .await?
+        .json::<Item>()
+        .await?;
+    Ok(item)
+}
+
+pub async fn get_world(world_id: i32, client: &reqwest::Client) -> Result<World> {
+    let world = client
+        .get(format!("https://xivapi.com/World/{}", world_id))
+        .send()
+        .await?
+        .json::<World>()
+        .await?;
+    Ok(world)
+}